@@ -0,0 +1,240 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use smithay::reexports::wayland_protocols::xdg_shell::server::xdg_toplevel;
+use smithay::reexports::wayland_server::protocol::wl_pointer::ButtonState;
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::utils::{Logical, Point};
+use smithay::wayland::compositor;
+use smithay::wayland::seat::{AxisFrame, PointerGrab, PointerGrabStartData, PointerInnerHandle};
+use smithay::wayland::shell::xdg::XdgToplevelSurfaceRoleAttributes;
+use smithay::wayland::Serial;
+
+use smithay::desktop::Window;
+
+use crate::surface_data::{ResizeData, ResizeEdge, ResizeState, SurfaceData};
+
+use super::{Inner, ShellHandler};
+
+/// Horizontal distance, in logical pixels, a drag has to travel past the
+/// last column swap before `MoveSurfaceGrab` asks the layout to reorder the
+/// window into the next column over -- without this, every pixel of motion
+/// would re-trigger `move_window_column`.
+const COLUMN_SWAP_THRESHOLD: i32 = 40;
+
+/// Pointer grab driving an interactive move, started from
+/// `ShellEvent::WindowMove`. Tracks `initial_window_location + (pointer -
+/// grab_start)` on every motion event and leaves the final position in
+/// place on button release -- there is nothing further to commit, the
+/// window location is updated live. Under a column-based layout (e.g.
+/// `ScrollableTiling`), crossing a column boundary also asks the layout to
+/// reorder the window into that column, so dragging doesn't fight the next
+/// repack.
+pub struct MoveSurfaceGrab<D> {
+    pub start_data: PointerGrabStartData,
+    pub inner: Rc<RefCell<Inner<D>>>,
+    pub window: Window,
+    pub surface: WlSurface,
+    pub initial_window_location: Point<i32, Logical>,
+    /// The drag's horizontal delta at the last column swap, so repeated
+    /// crossings during one long drag each trigger exactly once.
+    pub reordered_delta_x: i32,
+}
+
+impl<D> PointerGrab for MoveSurfaceGrab<D>
+where
+    D: ShellHandler + 'static,
+{
+    fn motion(
+        &mut self,
+        handle: &mut PointerInnerHandle<'_>,
+        location: Point<f64, Logical>,
+        _focus: Option<(WlSurface, Point<i32, Logical>)>,
+        serial: Serial,
+        time: u32,
+    ) {
+        // No client gets pointer focus while the grab is active.
+        handle.motion(location, None, serial, time);
+
+        let delta = (location - self.start_data.location).to_i32_round();
+        let new_location = self.initial_window_location + delta;
+
+        let mut inner = self.inner.borrow_mut();
+        inner.windows.set_location(&self.window, new_location);
+
+        let unspent = delta.x - self.reordered_delta_x;
+        if unspent.abs() >= COLUMN_SWAP_THRESHOLD {
+            if let Some(output) = inner.active_output.clone() {
+                let towards_right = unspent > 0;
+                let placements = inner.layout.move_window_column(&output, &self.window, towards_right);
+                if !placements.is_empty() {
+                    self.reordered_delta_x = delta.x;
+                    inner.reposition(&placements);
+                }
+            }
+        }
+    }
+
+    fn button(
+        &mut self,
+        handle: &mut PointerInnerHandle<'_>,
+        button: u32,
+        state: ButtonState,
+        serial: Serial,
+        time: u32,
+    ) {
+        handle.button(button, state, serial, time);
+
+        // The grab is finished when the initiating button is released.
+        if handle.current_pressed().is_empty() {
+            handle.unset_grab(serial, time);
+        }
+    }
+
+    fn axis(&mut self, handle: &mut PointerInnerHandle<'_>, details: AxisFrame) {
+        handle.axis(details)
+    }
+
+    fn start_data(&self) -> &PointerGrabStartData {
+        &self.start_data
+    }
+}
+
+/// Pointer grab driving an interactive resize, started from
+/// `ShellEvent::WindowResize`. Drives the `ResizeState` state machine that
+/// `Inner::try_update_mapped` already consumes: the grab itself only clamps
+/// the requested size against the toplevel's min/max hints and asks the
+/// client to configure to it, the actual location adjustment for TOP/LEFT
+/// edges happens once the client commits a buffer of the new size.
+pub struct ResizeSurfaceGrab<D> {
+    pub start_data: PointerGrabStartData,
+    pub inner: Rc<RefCell<Inner<D>>>,
+    pub window: Window,
+    pub surface: WlSurface,
+    pub edges: ResizeEdge,
+    pub initial_window_location: Point<i32, Logical>,
+    pub initial_window_size: smithay::utils::Size<i32, Logical>,
+}
+
+impl<D> ResizeSurfaceGrab<D> {
+    fn clamp_size(
+        &self,
+        size: smithay::utils::Size<i32, Logical>,
+    ) -> smithay::utils::Size<i32, Logical> {
+        let (min_size, max_size) = compositor::with_states(&self.surface, |states| {
+            let attrs = states
+                .data_map
+                .get::<std::sync::Mutex<XdgToplevelSurfaceRoleAttributes>>()
+                .unwrap()
+                .lock()
+                .unwrap();
+            (attrs.min_size, attrs.max_size)
+        })
+        .unwrap_or_default();
+
+        let min_w = if min_size.w > 0 { min_size.w } else { 1 };
+        let min_h = if min_size.h > 0 { min_size.h } else { 1 };
+        let max_w = if max_size.w > 0 { max_size.w } else { i32::MAX };
+        let max_h = if max_size.h > 0 { max_size.h } else { i32::MAX };
+
+        smithay::utils::Size::from((
+            size.w.clamp(min_w, max_w),
+            size.h.clamp(min_h, max_h),
+        ))
+    }
+}
+
+impl<D> PointerGrab for ResizeSurfaceGrab<D>
+where
+    D: ShellHandler + 'static,
+{
+    fn motion(
+        &mut self,
+        handle: &mut PointerInnerHandle<'_>,
+        location: Point<f64, Logical>,
+        _focus: Option<(WlSurface, Point<i32, Logical>)>,
+        serial: Serial,
+        time: u32,
+    ) {
+        handle.motion(location, None, serial, time);
+
+        let delta = (location - self.start_data.location).to_i32_round();
+
+        let mut new_size = self.initial_window_size;
+        if self.edges.intersects(ResizeEdge::LEFT | ResizeEdge::RIGHT) {
+            new_size.w = if self.edges.intersects(ResizeEdge::LEFT) {
+                self.initial_window_size.w - delta.x
+            } else {
+                self.initial_window_size.w + delta.x
+            };
+        }
+        if self.edges.intersects(ResizeEdge::TOP | ResizeEdge::BOTTOM) {
+            new_size.h = if self.edges.intersects(ResizeEdge::TOP) {
+                self.initial_window_size.h - delta.y
+            } else {
+                self.initial_window_size.h + delta.y
+            };
+        }
+        let new_size = self.clamp_size(new_size);
+
+        SurfaceData::with_mut(&self.surface, |data| {
+            data.resize_state = match data.resize_state {
+                ResizeState::Resizing(mut resize_data) => {
+                    resize_data.edges = self.edges;
+                    resize_data.initial_window_location = self.initial_window_location;
+                    resize_data.initial_window_size = self.initial_window_size;
+                    ResizeState::Resizing(resize_data)
+                }
+                _ => ResizeState::Resizing(ResizeData {
+                    edges: self.edges,
+                    initial_window_location: self.initial_window_location,
+                    initial_window_size: self.initial_window_size,
+                }),
+            };
+        });
+
+        if let Some(toplevel) = self.window.toplevel().as_xdg() {
+            toplevel.with_pending_state(|state| {
+                state.states.set(xdg_toplevel::State::Resizing);
+                state.size = Some(new_size);
+            });
+            toplevel.send_configure();
+        }
+    }
+
+    fn button(
+        &mut self,
+        handle: &mut PointerInnerHandle<'_>,
+        button: u32,
+        state: ButtonState,
+        serial: Serial,
+        time: u32,
+    ) {
+        handle.button(button, state, serial, time);
+
+        if handle.current_pressed().is_empty() {
+            handle.unset_grab(serial, time);
+
+            if let Some(toplevel) = self.window.toplevel().as_xdg() {
+                toplevel.with_pending_state(|state| {
+                    state.states.unset(xdg_toplevel::State::Resizing);
+                });
+                toplevel.send_configure();
+            }
+
+            SurfaceData::with_mut(&self.surface, |data| {
+                if let ResizeState::Resizing(resize_data) = data.resize_state {
+                    data.resize_state = ResizeState::WaitingForFinalAck(resize_data, serial);
+                }
+            });
+        }
+    }
+
+    fn axis(&mut self, handle: &mut PointerInnerHandle<'_>, details: AxisFrame) {
+        handle.axis(details)
+    }
+
+    fn start_data(&self) -> &PointerGrabStartData {
+        &self.start_data
+    }
+}
@@ -0,0 +1,20 @@
+use smithay::reexports::wayland_server::protocol::wl_output::WlOutput;
+
+/// Maximized/fullscreen state a client requested on its toplevel before the
+/// very first configure went out. Buffered on the `NotMappedList` entry and
+/// resolved at map time (maximized against the output's usable area,
+/// fullscreen against its full geometry), so the window
+/// comes up at the right size on the first frame instead of needing a
+/// second round-trip.
+#[derive(Debug, Clone)]
+pub enum InitialWindowState {
+    Normal,
+    Maximized,
+    Fullscreen { output: Option<WlOutput> },
+}
+
+impl Default for InitialWindowState {
+    fn default() -> Self {
+        InitialWindowState::Normal
+    }
+}
@@ -0,0 +1,375 @@
+use std::collections::HashMap;
+
+use smithay::desktop::Window;
+use smithay::reexports::wayland_server::protocol::wl_output::WlOutput;
+use smithay::utils::{Logical, Point, Rectangle, Size};
+use smithay::wayland::output::Output;
+
+/// Width presets a column can cycle through, expressed as a fraction of the
+/// output width. `cycle_column_width` walks this list and wraps around.
+const WIDTH_PRESETS: [f64; 3] = [1.0 / 3.0, 1.0 / 2.0, 2.0 / 3.0];
+
+/// A pluggable window arrangement strategy.
+///
+/// `ShellManager` drives every mapped [`Window`] through whichever `Layout`
+/// is installed. The default is [`FloatingLayout`], which leaves windows
+/// exactly where the client (or the user, via move/resize grabs) put them.
+/// [`ScrollableTiling`] is the alternative PaperWM-style strip.
+pub trait Layout {
+    /// A new toplevel was mapped on `output`. Returns the windows that need
+    /// to be repositioned as a result (including `window` itself).
+    fn window_created(
+        &mut self,
+        output: &WlOutput,
+        window: Window,
+    ) -> Vec<(Window, Rectangle<i32, Logical>)>;
+
+    /// A mapped toplevel was destroyed. Returns the windows that need to be
+    /// repositioned to fill the gap it leaves behind.
+    fn window_removed(
+        &mut self,
+        output: &WlOutput,
+        window: &Window,
+    ) -> Vec<(Window, Rectangle<i32, Logical>)>;
+
+    /// The usable geometry of `output` changed (resolution change, new
+    /// exclusive zone, ...). Returns the windows that need repositioning.
+    fn output_geometry_changed(
+        &mut self,
+        output: &WlOutput,
+        geometry: Rectangle<i32, Logical>,
+    ) -> Vec<(Window, Rectangle<i32, Logical>)>;
+
+    /// `window` became the active window on `output`; the layout may want to
+    /// scroll its view to bring it fully into frame.
+    fn activate(
+        &mut self,
+        output: &WlOutput,
+        window: &Window,
+    ) -> Vec<(Window, Rectangle<i32, Logical>)>;
+
+    /// Cycle the width preset of the column containing `window`.
+    fn cycle_column_width(
+        &mut self,
+        output: &WlOutput,
+        window: &Window,
+    ) -> Vec<(Window, Rectangle<i32, Logical>)>;
+
+    /// Move `window` into the column to the left/right of its current one,
+    /// used while dragging a window between columns. If `window` is alone
+    /// in its column, that column swaps places with the neighbour instead.
+    fn move_window_column(
+        &mut self,
+        output: &WlOutput,
+        window: &Window,
+        towards_right: bool,
+    ) -> Vec<(Window, Rectangle<i32, Logical>)>;
+}
+
+/// The default layout: windows stay exactly where they are placed, nothing
+/// is auto-arranged. This preserves Anodium's pre-existing floating
+/// behavior.
+#[derive(Default)]
+pub struct FloatingLayout;
+
+impl Layout for FloatingLayout {
+    fn window_created(
+        &mut self,
+        _output: &WlOutput,
+        _window: Window,
+    ) -> Vec<(Window, Rectangle<i32, Logical>)> {
+        Vec::new()
+    }
+
+    fn window_removed(
+        &mut self,
+        _output: &WlOutput,
+        _window: &Window,
+    ) -> Vec<(Window, Rectangle<i32, Logical>)> {
+        Vec::new()
+    }
+
+    fn output_geometry_changed(
+        &mut self,
+        _output: &WlOutput,
+        _geometry: Rectangle<i32, Logical>,
+    ) -> Vec<(Window, Rectangle<i32, Logical>)> {
+        Vec::new()
+    }
+
+    fn activate(
+        &mut self,
+        _output: &WlOutput,
+        _window: &Window,
+    ) -> Vec<(Window, Rectangle<i32, Logical>)> {
+        Vec::new()
+    }
+
+    fn cycle_column_width(
+        &mut self,
+        _output: &WlOutput,
+        _window: &Window,
+    ) -> Vec<(Window, Rectangle<i32, Logical>)> {
+        Vec::new()
+    }
+
+    fn move_window_column(
+        &mut self,
+        _output: &WlOutput,
+        _window: &Window,
+        _towards_right: bool,
+    ) -> Vec<(Window, Rectangle<i32, Logical>)> {
+        Vec::new()
+    }
+}
+
+/// A single column of the scrollable strip: one or more windows stacked
+/// vertically, all sharing the column's width.
+struct Column {
+    windows: Vec<Window>,
+    /// Index into [`WIDTH_PRESETS`].
+    width_preset: usize,
+}
+
+impl Column {
+    fn new(window: Window) -> Self {
+        Self {
+            windows: vec![window],
+            width_preset: 1,
+        }
+    }
+
+    fn width(&self, output_geometry: Rectangle<i32, Logical>) -> i32 {
+        (output_geometry.size.w as f64 * WIDTH_PRESETS[self.width_preset]).round() as i32
+    }
+
+    fn contains(&self, window: &Window) -> bool {
+        self.windows.iter().any(|w| w == window)
+    }
+}
+
+/// Per-output PaperWM-style strip: an infinite horizontal row of [`Column`]s,
+/// each the full height of the output.
+struct Strip {
+    columns: Vec<Column>,
+    active: usize,
+    view_offset: i32,
+    output_geometry: Rectangle<i32, Logical>,
+}
+
+impl Strip {
+    fn new(output_geometry: Rectangle<i32, Logical>) -> Self {
+        Self {
+            columns: Vec::new(),
+            active: 0,
+            view_offset: 0,
+            output_geometry,
+        }
+    }
+
+    fn column_of(&self, window: &Window) -> Option<usize> {
+        self.columns.iter().position(|c| c.contains(window))
+    }
+
+    /// x offset (before `view_offset` is applied) of the left edge of column `idx`.
+    fn column_x(&self, idx: usize) -> i32 {
+        self.columns[..idx]
+            .iter()
+            .map(|c| c.width(self.output_geometry))
+            .sum()
+    }
+
+    /// Recompute the geometry of every window in every column and return the
+    /// full set of placements.
+    fn repack(&mut self) -> Vec<(Window, Rectangle<i32, Logical>)> {
+        let mut placements = Vec::new();
+        let mut x = 0;
+        for column in &self.columns {
+            let width = column.width(self.output_geometry);
+            let height = self.output_geometry.size.h / column.windows.len().max(1) as i32;
+
+            for (row, window) in column.windows.iter().enumerate() {
+                let location = Point::from((
+                    self.output_geometry.loc.x + x - self.view_offset,
+                    self.output_geometry.loc.y + height * row as i32,
+                ));
+                let size = Size::from((width, height));
+                placements.push((window.clone(), Rectangle::from_loc_and_size(location, size)));
+            }
+
+            x += width;
+        }
+        placements
+    }
+
+    /// Scroll the strip so that column `idx` is fully visible, centering it
+    /// when it fits within the output width.
+    fn scroll_to_column(&mut self, idx: usize) {
+        let column_x = self.column_x(idx);
+        let column_width = self.columns[idx].width(self.output_geometry);
+        let output_width = self.output_geometry.size.w;
+
+        self.view_offset = if column_width <= output_width {
+            column_x - (output_width - column_width) / 2
+        } else if column_x < self.view_offset {
+            column_x
+        } else if column_x + column_width > self.view_offset + output_width {
+            column_x + column_width - output_width
+        } else {
+            self.view_offset
+        };
+    }
+}
+
+/// A PaperWM-style scrollable-tiling layout: each output is an infinite
+/// horizontal strip of columns, columns hold windows stacked vertically.
+#[derive(Default)]
+pub struct ScrollableTiling {
+    strips: HashMap<WlOutput, Strip>,
+}
+
+impl Layout for ScrollableTiling {
+    fn window_created(
+        &mut self,
+        output: &WlOutput,
+        window: Window,
+    ) -> Vec<(Window, Rectangle<i32, Logical>)> {
+        let strip = self.strips.entry(output.clone()).or_insert_with(|| {
+            // Seed with the output's actual current geometry rather than a
+            // zero-sized placeholder -- `output_geometry_changed` only
+            // fires on later resolution changes, so without this the very
+            // first windows placed on an output would be sized 0x0.
+            let geometry = Output::from_resource(output)
+                .map(|output| output.geometry())
+                .unwrap_or_else(|| Rectangle::from_loc_and_size((0, 0), (0, 0)));
+            Strip::new(geometry)
+        });
+
+        let insert_at = if strip.columns.is_empty() {
+            0
+        } else {
+            strip.active + 1
+        };
+        strip.columns.insert(insert_at, Column::new(window));
+        strip.active = insert_at;
+        strip.scroll_to_column(strip.active);
+
+        strip.repack()
+    }
+
+    fn window_removed(
+        &mut self,
+        output: &WlOutput,
+        window: &Window,
+    ) -> Vec<(Window, Rectangle<i32, Logical>)> {
+        let Some(strip) = self.strips.get_mut(output) else {
+            return Vec::new();
+        };
+
+        if let Some(idx) = strip.column_of(window) {
+            let column = &mut strip.columns[idx];
+            column.windows.retain(|w| w != window);
+            if column.windows.is_empty() {
+                strip.columns.remove(idx);
+                strip.active = strip.active.min(strip.columns.len().saturating_sub(1));
+            }
+        }
+
+        if strip.columns.is_empty() {
+            return Vec::new();
+        }
+
+        strip.scroll_to_column(strip.active);
+        strip.repack()
+    }
+
+    fn output_geometry_changed(
+        &mut self,
+        output: &WlOutput,
+        geometry: Rectangle<i32, Logical>,
+    ) -> Vec<(Window, Rectangle<i32, Logical>)> {
+        let strip = self
+            .strips
+            .entry(output.clone())
+            .or_insert_with(|| Strip::new(geometry));
+        strip.output_geometry = geometry;
+
+        if strip.columns.is_empty() {
+            return Vec::new();
+        }
+
+        strip.scroll_to_column(strip.active);
+        strip.repack()
+    }
+
+    fn activate(
+        &mut self,
+        output: &WlOutput,
+        window: &Window,
+    ) -> Vec<(Window, Rectangle<i32, Logical>)> {
+        let Some(strip) = self.strips.get_mut(output) else {
+            return Vec::new();
+        };
+        let Some(idx) = strip.column_of(window) else {
+            return Vec::new();
+        };
+
+        strip.active = idx;
+        strip.scroll_to_column(idx);
+        strip.repack()
+    }
+
+    fn cycle_column_width(
+        &mut self,
+        output: &WlOutput,
+        window: &Window,
+    ) -> Vec<(Window, Rectangle<i32, Logical>)> {
+        let Some(strip) = self.strips.get_mut(output) else {
+            return Vec::new();
+        };
+        let Some(idx) = strip.column_of(window) else {
+            return Vec::new();
+        };
+
+        let column = &mut strip.columns[idx];
+        column.width_preset = (column.width_preset + 1) % WIDTH_PRESETS.len();
+        strip.scroll_to_column(strip.active);
+        strip.repack()
+    }
+
+    fn move_window_column(
+        &mut self,
+        output: &WlOutput,
+        window: &Window,
+        towards_right: bool,
+    ) -> Vec<(Window, Rectangle<i32, Logical>)> {
+        let Some(strip) = self.strips.get_mut(output) else {
+            return Vec::new();
+        };
+        let Some(from) = strip.column_of(window) else {
+            return Vec::new();
+        };
+
+        let to = if towards_right {
+            (from + 1).min(strip.columns.len() - 1)
+        } else {
+            from.saturating_sub(1)
+        };
+        if to == from {
+            return Vec::new();
+        }
+
+        strip.columns[from].windows.retain(|w| w != window);
+        if strip.columns[from].windows.is_empty() {
+            strip.columns.remove(from);
+            strip.columns.insert(to, Column::new(window.clone()));
+            strip.active = to;
+        } else {
+            strip.columns[to].windows.push(window.clone());
+            strip.active = to;
+        }
+
+        strip.scroll_to_column(strip.active);
+        strip.repack()
+    }
+}
@@ -2,7 +2,7 @@ use smithay::desktop::{self, LayerSurface, PopupKind, PopupManager};
 use smithay::reexports::wayland_server::protocol::wl_output::WlOutput;
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use smithay::reexports::wayland_server::{DispatchData, Display};
-use smithay::utils::{Logical, Point};
+use smithay::utils::{Logical, Point, Rectangle, Size};
 use smithay::wayland::compositor::{self, TraversalAction};
 use smithay::wayland::seat::{PointerGrabStartData, Seat};
 use smithay::wayland::shell::wlr_layer::{
@@ -27,11 +27,32 @@ mod xdg;
 
 mod utils;
 
+mod layout;
+pub use layout::{FloatingLayout, Layout, ScrollableTiling};
+
+mod grabs;
+pub use grabs::{MoveSurfaceGrab, ResizeSurfaceGrab};
+
+mod positioner;
+mod popup;
+
+mod initial_state;
+pub use initial_state::InitialWindowState;
+
 #[cfg(feature = "xwayland")]
 pub mod xwayland;
 #[cfg(feature = "xwayland")]
 pub use xwayland::X11Surface;
 
+#[cfg(feature = "xwayland")]
+use crate::Anodium;
+#[cfg(feature = "xwayland")]
+use smithay::reexports::calloop::LoopHandle;
+#[cfg(feature = "xwayland")]
+use smithay::reexports::wayland_server::Client;
+#[cfg(feature = "xwayland")]
+use std::os::unix::net::UnixStream;
+
 pub trait ShellHandler {
     fn on_shell_event(&mut self, event: ShellEvent);
 }
@@ -60,6 +81,7 @@ pub enum ShellEvent {
         window: desktop::Window,
         new_location_x: Option<i32>,
         new_location_y: Option<i32>,
+        new_size: Option<Size<i32, Logical>>,
     },
 
     WindowMaximize {
@@ -122,6 +144,23 @@ pub enum ShellEvent {
         surface: WlSurface,
         configure: LayerSurfaceConfigure,
     },
+
+    //
+    // Keybinding-triggered actions
+    //
+    // These name an intent rather than a specific window: the handler is
+    // the one tracking which window is active on which output, so it
+    // resolves that itself instead of the binding dispatch doing it.
+    //
+    CloseActiveWindow,
+    FocusColumn {
+        towards_right: bool,
+    },
+    ToggleMaximizeActiveWindow,
+    ToggleFullscreenActiveWindow,
+    MoveActiveWindowToOutput {
+        index: usize,
+    },
 }
 
 struct Inner<D> {
@@ -130,6 +169,14 @@ struct Inner<D> {
     layers: ShellLayerList,
 
     popup_manager: PopupManager,
+
+    // The output new windows are placed on by the layout, and the output
+    // whose strip is scrolled by `Layout::activate`. Tracked here rather
+    // than on `Window` itself, since floating windows have no notion of
+    // "their" output.
+    active_output: Option<WlOutput>,
+    layout: Box<dyn Layout>,
+
     _pd: PhantomData<D>,
 }
 
@@ -137,6 +184,51 @@ impl<D> Inner<D>
 where
     D: ShellHandler + 'static,
 {
+    // Configure every window the layout placed to its computed geometry and
+    // record its new location in `windows`, without notifying `handler` --
+    // the half of `apply_layout` interactive grabs can use, since they don't
+    // have a handler reference to hand a `ShellEvent` to.
+    pub(super) fn reposition(&mut self, placements: &[(Window, Rectangle<i32, Logical>)]) {
+        for (window, geometry) in placements {
+            if let Some(toplevel) = window.toplevel().as_xdg() {
+                toplevel.with_pending_state(|state| {
+                    state.size = Some(geometry.size);
+                });
+                toplevel.send_configure();
+            }
+
+            self.windows.set_location(window, geometry.loc);
+        }
+    }
+
+    // Configure every window the layout placed to its computed geometry --
+    // locations already have the output's `view_offset` baked in by the
+    // layout itself -- and emit a `WindowGotResized` for each so the size
+    // actually reaches the client instead of just the location.
+    fn apply_layout(
+        &mut self,
+        placements: Vec<(Window, Rectangle<i32, Logical>)>,
+        handler: &mut D,
+    ) {
+        self.reposition(&placements);
+
+        for (window, geometry) in placements {
+            handler.on_shell_event(ShellEvent::WindowGotResized {
+                window,
+                new_location_x: Some(geometry.loc.x),
+                new_location_y: Some(geometry.loc.y),
+                new_size: Some(geometry.size),
+            });
+        }
+    }
+
+    fn window_closed(&mut self, window: &Window, handler: &mut D) {
+        if let Some(output) = self.active_output.clone() {
+            let placements = self.layout.window_removed(&output, window);
+            self.apply_layout(placements, handler);
+        }
+    }
+
     // Try to updated mapped surface
     fn try_update_mapped(&mut self, surface: &WlSurface, handler: &mut D) {
         if let Some(window) = self.windows.find_mut(surface) {
@@ -198,16 +290,46 @@ where
                     window: window.clone(),
                     new_location_x: new_location.0,
                     new_location_y: new_location.1,
+                    new_size: None,
                 })
             }
         }
     }
 
+    // Resolve a buffered `InitialWindowState` against the currently active
+    // output, so the very first configure for a toplevel already carries
+    // the right maximized/fullscreen geometry.
+    fn resolve_initial_geometry(
+        &self,
+        state: &InitialWindowState,
+    ) -> Option<smithay::utils::Rectangle<i32, Logical>> {
+        let default_output = self.active_output.as_ref()?;
+        match state {
+            InitialWindowState::Normal => None,
+            InitialWindowState::Maximized => Some(self.output_work_area(default_output)),
+            InitialWindowState::Fullscreen { output } => {
+                Some(self.output_geometry(output.as_ref().unwrap_or(default_output)))
+            }
+        }
+    }
+
     // Try to map surface
     fn try_map_unmaped(&mut self, surface: &WlSurface, handler: &mut D) {
+        if let Some(state) = self.not_mapped_list.pending_state(surface) {
+            if let Some(geometry) = self.resolve_initial_geometry(&state) {
+                self.not_mapped_list.configure_initial(surface, geometry);
+            }
+        }
+
         if let Some(window) = self.not_mapped_list.try_window_map(surface) {
             self.windows.push(window.clone());
-            handler.on_shell_event(ShellEvent::WindowCreated { window });
+            handler.on_shell_event(ShellEvent::WindowCreated {
+                window: window.clone(),
+            });
+
+            if let Some(output) = self.active_output.clone() {
+                self.apply_layout(self.layout.window_created(&output, window), handler);
+            }
         }
 
         if let Some(popup) = self.popup_manager.find_popup(surface) {
@@ -223,6 +345,9 @@ where
             })
             .unwrap();
             if !initial_configure_sent {
+                if let Some(geometry) = self.solve_popup_geometry(popup) {
+                    popup.with_pending_state(|state| state.geometry = geometry);
+                }
                 popup.send_configure().expect("Initial configure failed");
             }
         }
@@ -258,25 +383,6 @@ where
         // Update mapped windows
         self.try_update_mapped(&surface, handler);
 
-        // TODO:
-        // if let Some(popup) = self.window_map.borrow().popups().find(surface) {
-        //     let PopupKind::Xdg(ref popup) = popup.popup;
-        //     let initial_configure_sent = with_states(surface, |states| {
-        //         states
-        //             .data_map
-        //             .get::<Mutex<XdgPopupSurfaceRoleAttributes>>()
-        //             .unwrap()
-        //             .lock()
-        //             .unwrap()
-        //             .initial_configure_sent
-        //     })
-        //     .unwrap();
-        //     if !initial_configure_sent {
-        //         // TODO: properly recompute the geometry with the whole of positioner state
-        //         popup.send_configure();
-        //     }
-        // }
-
         if let Some(layer) = self.layers.find(&surface) {
             let initial_configure_sent = compositor::with_states(&surface, |states| {
                 states
@@ -313,6 +419,10 @@ impl<D> ShellManager<D> {
             layers: Default::default(),
 
             popup_manager: PopupManager::new(None),
+
+            active_output: None,
+            layout: Box::new(FloatingLayout::default()),
+
             _pd: PhantomData::<D>,
         }));
 
@@ -331,10 +441,13 @@ impl<D> ShellManager<D> {
             display,
             {
                 let inner = inner.clone();
+                let inner_handle = inner.clone();
                 move |request, mut ddata| {
-                    inner
-                        .borrow_mut()
-                        .xdg_shell_request(request, ddata.get().unwrap());
+                    inner.borrow_mut().xdg_shell_request(
+                        request,
+                        ddata.get().unwrap(),
+                        &inner_handle,
+                    );
                 }
             },
             None,
@@ -357,6 +470,84 @@ impl<D> ShellManager<D> {
         Self { inner }
     }
 
+    /// Replace the active window-arrangement strategy. Defaults to
+    /// [`FloatingLayout`]; pass a [`ScrollableTiling`] to switch to the
+    /// PaperWM-style scrollable strip.
+    pub fn set_layout(&mut self, layout: impl Layout + 'static)
+    where
+        D: ShellHandler + 'static,
+    {
+        self.inner.borrow_mut().layout = Box::new(layout);
+    }
+
+    /// Tell the layout which output new windows should be placed on.
+    pub fn set_active_output(&mut self, output: WlOutput) {
+        self.inner.borrow_mut().active_output = Some(output);
+    }
+
+    /// Notify the layout that `output`'s usable geometry changed (resolution
+    /// change, new layer-shell exclusive zone, ...), re-arranging whatever
+    /// is already placed on it.
+    pub fn output_geometry_changed(
+        &mut self,
+        output: &WlOutput,
+        geometry: Rectangle<i32, Logical>,
+        handler: &mut D,
+    ) where
+        D: ShellHandler + 'static,
+    {
+        let mut inner = self.inner.borrow_mut();
+        let placements = inner.layout.output_geometry_changed(output, geometry);
+        inner.apply_layout(placements, handler);
+    }
+
+    pub fn window_closed(&mut self, window: &Window, handler: &mut D)
+    where
+        D: ShellHandler + 'static,
+    {
+        self.inner.borrow_mut().window_closed(window, handler);
+    }
+
+    /// Bring `window`'s column fully into view and mark it active.
+    pub fn activate_window(&mut self, window: &Window, handler: &mut D)
+    where
+        D: ShellHandler + 'static,
+    {
+        let mut inner = self.inner.borrow_mut();
+        if let Some(output) = inner.active_output.clone() {
+            let placements = inner.layout.activate(&output, window);
+            inner.apply_layout(placements, handler);
+        }
+    }
+
+    /// Cycle `window`'s column through the width presets (1/3, 1/2, 2/3).
+    pub fn cycle_column_width(&mut self, window: &Window, handler: &mut D)
+    where
+        D: ShellHandler + 'static,
+    {
+        let mut inner = self.inner.borrow_mut();
+        if let Some(output) = inner.active_output.clone() {
+            let placements = inner.layout.cycle_column_width(&output, window);
+            inner.apply_layout(placements, handler);
+        }
+    }
+
+    /// Move `window` into the neighbouring column, used when dragging a
+    /// window between columns of the strip. A window alone in its column
+    /// swaps that column with the neighbour instead.
+    pub fn move_window_column(&mut self, window: &Window, towards_right: bool, handler: &mut D)
+    where
+        D: ShellHandler + 'static,
+    {
+        let mut inner = self.inner.borrow_mut();
+        if let Some(output) = inner.active_output.clone() {
+            let placements = inner
+                .layout
+                .move_window_column(&output, window, towards_right);
+            inner.apply_layout(placements, handler);
+        }
+    }
+
     #[cfg(feature = "xwayland")]
     pub fn xwayland_ready(
         &mut self,
@@ -0,0 +1,159 @@
+use std::cell::RefCell;
+use std::sync::Mutex;
+
+use smithay::desktop::{LayerSurface, PopupKind};
+use smithay::reexports::wayland_server::protocol::wl_output::WlOutput;
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::utils::{Logical, Point, Rectangle};
+use smithay::wayland::compositor;
+use smithay::wayland::shell::wlr_layer::{Anchor, LayerSurfaceAttributes};
+use smithay::wayland::shell::xdg::{PopupSurface, PositionerState};
+
+use super::positioner;
+use super::{Inner, ShellHandler};
+
+/// The positioner a popup was last created or repositioned with, stashed on
+/// the surface's `data_map` so `reposition` requests and the initial
+/// configure can both solve against it.
+struct StoredPositioner(RefCell<PositionerState>);
+
+/// The geometry the positioner solver last produced for a popup, read back
+/// by `surface_location` so nested child popups can anchor off it. A
+/// `reposition` solves afresh from its new positioner and does not read it.
+struct SolvedGeometry(RefCell<Rectangle<i32, Logical>>);
+
+pub(super) fn store_positioner(surface: &WlSurface, positioner: PositionerState) {
+    compositor::with_states(surface, |states| {
+        states
+            .data_map
+            .insert_if_missing(|| StoredPositioner(RefCell::new(positioner.clone())));
+        *states
+            .data_map
+            .get::<StoredPositioner>()
+            .unwrap()
+            .0
+            .borrow_mut() = positioner;
+    })
+    .ok();
+}
+
+fn stored_positioner(surface: &WlSurface) -> Option<PositionerState> {
+    compositor::with_states(surface, |states| {
+        states
+            .data_map
+            .get::<StoredPositioner>()
+            .map(|stored| stored.0.borrow().clone())
+    })
+    .ok()
+    .flatten()
+}
+
+fn store_geometry(surface: &WlSurface, geometry: Rectangle<i32, Logical>) {
+    compositor::with_states(surface, |states| {
+        states
+            .data_map
+            .insert_if_missing(|| SolvedGeometry(RefCell::new(geometry)));
+        *states.data_map.get::<SolvedGeometry>().unwrap().0.borrow_mut() = geometry;
+    })
+    .ok();
+}
+
+fn stored_geometry(surface: &WlSurface) -> Option<Rectangle<i32, Logical>> {
+    compositor::with_states(surface, |states| {
+        states.data_map.get::<SolvedGeometry>().map(|solved| *solved.0.borrow())
+    })
+    .ok()
+    .flatten()
+}
+
+impl<D> Inner<D>
+where
+    D: ShellHandler + 'static,
+{
+    /// Resolve `popup`'s geometry against its stored positioner and the
+    /// active output's work area, caching the result for its child popups.
+    pub(super) fn solve_popup_geometry(&self, popup: &PopupSurface) -> Option<Rectangle<i32, Logical>> {
+        let positioner = stored_positioner(popup.wl_surface())?;
+        let parent = popup.get_parent_surface()?;
+
+        let parent_location = self.surface_location(&parent)?;
+
+        let anchor_rect = Rectangle::from_loc_and_size(
+            parent_location + positioner.anchor_rect.loc,
+            positioner.anchor_rect.size,
+        );
+
+        let output = self.active_output.as_ref()?;
+        let work_area = self.output_work_area(output);
+
+        let geometry = positioner::place_popup(&positioner, anchor_rect, work_area);
+        store_geometry(popup.wl_surface(), geometry);
+
+        Some(geometry)
+    }
+
+    /// The top-left of `surface` in the same (global, logical) coordinate
+    /// space as `output_work_area`. `surface` is either a mapped toplevel or
+    /// another popup -- nested submenus anchor off their immediate parent
+    /// popup rather than the root toplevel, so this walks up the parent
+    /// chain, reusing each ancestor popup's already-solved geometry, until a
+    /// toplevel is reached.
+    fn surface_location(&self, surface: &WlSurface) -> Option<Point<i32, Logical>> {
+        if let Some(window) = self.windows.find(surface) {
+            let location = self.windows.location(&window);
+            let geometry = window.geometry();
+            return Some(location + geometry.loc);
+        }
+
+        let PopupKind::Xdg(parent_popup) = self.popup_manager.find_popup(surface)?;
+        stored_geometry(parent_popup.wl_surface()).map(|geometry| geometry.loc)
+    }
+
+    /// The full geometry of `output`, panels/bars and all -- what a
+    /// fullscreen window should cover, as opposed to [`Self::output_work_area`].
+    pub(super) fn output_geometry(&self, output: &WlOutput) -> Rectangle<i32, Logical> {
+        smithay::wayland::output::Output::from_resource(output)
+            .map(|output| output.geometry())
+            .unwrap_or_else(|| Rectangle::from_loc_and_size((0, 0), (i32::MAX, i32::MAX)))
+    }
+
+    /// Usable area of `output`, in the same (global, logical) coordinate
+    /// space as window locations -- the full output geometry with every
+    /// mapped layer shell surface's exclusive zone subtracted, so maximized
+    /// windows and positioned popups don't end up under panels/bars.
+    pub(super) fn output_work_area(&self, output: &WlOutput) -> Rectangle<i32, Logical> {
+        let mut area = self.output_geometry(output);
+
+        for layer in self.layers.for_output(output) {
+            let Some((anchor, exclusive_zone)) = layer_exclusive_state(layer) else {
+                continue;
+            };
+            if exclusive_zone <= 0 {
+                continue;
+            }
+
+            if anchor.contains(Anchor::Top) {
+                area.loc.y += exclusive_zone;
+                area.size.h -= exclusive_zone;
+            } else if anchor.contains(Anchor::Bottom) {
+                area.size.h -= exclusive_zone;
+            } else if anchor.contains(Anchor::Left) {
+                area.loc.x += exclusive_zone;
+                area.size.w -= exclusive_zone;
+            } else if anchor.contains(Anchor::Right) {
+                area.size.w -= exclusive_zone;
+            }
+        }
+
+        area
+    }
+}
+
+fn layer_exclusive_state(layer: &LayerSurface) -> Option<(Anchor, i32)> {
+    compositor::with_states(layer.layer_surface().wl_surface(), |states| {
+        let attrs = states.data_map.get::<Mutex<LayerSurfaceAttributes>>()?.lock().unwrap();
+        Some((attrs.anchor, attrs.exclusive_zone))
+    })
+    .ok()
+    .flatten()
+}
@@ -0,0 +1,211 @@
+//! `xdg_positioner` constraint solving.
+//!
+//! Turns a client's `PositionerState` plus the popup's anchor rectangle (in
+//! the parent's coordinate space) and the output's usable work area into a
+//! concrete popup geometry, applying the `constraint_adjustment` flags in
+//! the priority order the protocol specifies: flip, then slide, then
+//! resize.
+
+use smithay::reexports::wayland_protocols::xdg_shell::server::xdg_positioner::{
+    Anchor, ConstraintAdjustment, Gravity,
+};
+use smithay::utils::{Logical, Point, Rectangle, Size};
+use smithay::wayland::shell::xdg::PositionerState;
+
+/// Resolve `positioner`'s placement of a popup anchored to `anchor_rect`
+/// (already translated into the same coordinate space as `work_area`),
+/// constrained to fit within `work_area`.
+pub fn place_popup(
+    positioner: &PositionerState,
+    anchor_rect: Rectangle<i32, Logical>,
+    work_area: Rectangle<i32, Logical>,
+) -> Rectangle<i32, Logical> {
+    let adjustment = positioner.constraint_adjustment;
+    let size = positioner.rect_size;
+
+    let mut anchor_edges = positioner.anchor_edges;
+    let mut gravity = positioner.gravity;
+    let mut rect = compute(size, positioner.offset, anchor_rect, anchor_edges, gravity);
+
+    if adjustment.contains(ConstraintAdjustment::FlipX) && !fits_x(rect, work_area) {
+        let flipped_anchor = flip_anchor_x(anchor_edges);
+        let flipped_gravity = flip_gravity_x(gravity);
+        let flipped = compute(size, positioner.offset, anchor_rect, flipped_anchor, flipped_gravity);
+        // Only keep the flip if it fits entirely -- otherwise the original
+        // placement is kept and left to slide/resize.
+        if fits_x(flipped, work_area) {
+            rect = flipped;
+            anchor_edges = flipped_anchor;
+            gravity = flipped_gravity;
+        }
+    }
+
+    if adjustment.contains(ConstraintAdjustment::FlipY) && !fits_y(rect, work_area) {
+        let flipped_anchor = flip_anchor_y(anchor_edges);
+        let flipped_gravity = flip_gravity_y(gravity);
+        let flipped = compute(size, positioner.offset, anchor_rect, flipped_anchor, flipped_gravity);
+        if fits_y(flipped, work_area) {
+            rect = flipped;
+        }
+    }
+
+    if adjustment.contains(ConstraintAdjustment::SlideX) {
+        rect = slide_x(rect, anchor_rect, work_area);
+    }
+    if adjustment.contains(ConstraintAdjustment::SlideY) {
+        rect = slide_y(rect, anchor_rect, work_area);
+    }
+
+    if adjustment.contains(ConstraintAdjustment::ResizeX) {
+        rect = resize_x(rect, work_area);
+    }
+    if adjustment.contains(ConstraintAdjustment::ResizeY) {
+        rect = resize_y(rect, work_area);
+    }
+
+    rect
+}
+
+fn anchor_point(rect: Rectangle<i32, Logical>, anchor: Anchor) -> Point<i32, Logical> {
+    let x = match anchor {
+        Anchor::Left | Anchor::TopLeft | Anchor::BottomLeft => rect.loc.x,
+        Anchor::Right | Anchor::TopRight | Anchor::BottomRight => rect.loc.x + rect.size.w,
+        _ => rect.loc.x + rect.size.w / 2,
+    };
+    let y = match anchor {
+        Anchor::Top | Anchor::TopLeft | Anchor::TopRight => rect.loc.y,
+        Anchor::Bottom | Anchor::BottomLeft | Anchor::BottomRight => rect.loc.y + rect.size.h,
+        _ => rect.loc.y + rect.size.h / 2,
+    };
+    (x, y).into()
+}
+
+/// Offset from the gravity-resolved anchor point to the popup's top-left
+/// corner: the popup extends away from the anchor in the gravity direction.
+fn gravity_origin(gravity: Gravity, size: Size<i32, Logical>) -> Point<i32, Logical> {
+    let x = match gravity {
+        Gravity::Left | Gravity::TopLeft | Gravity::BottomLeft => -size.w,
+        Gravity::Right | Gravity::TopRight | Gravity::BottomRight => 0,
+        _ => -size.w / 2,
+    };
+    let y = match gravity {
+        Gravity::Top | Gravity::TopLeft | Gravity::TopRight => -size.h,
+        Gravity::Bottom | Gravity::BottomLeft | Gravity::BottomRight => 0,
+        _ => -size.h / 2,
+    };
+    (x, y).into()
+}
+
+fn compute(
+    size: Size<i32, Logical>,
+    offset: Point<i32, Logical>,
+    anchor_rect: Rectangle<i32, Logical>,
+    anchor_edges: Anchor,
+    gravity: Gravity,
+) -> Rectangle<i32, Logical> {
+    let anchor = anchor_point(anchor_rect, anchor_edges);
+    let loc = anchor + gravity_origin(gravity, size) + offset;
+    Rectangle::from_loc_and_size(loc, size)
+}
+
+fn flip_anchor_x(anchor: Anchor) -> Anchor {
+    match anchor {
+        Anchor::Left => Anchor::Right,
+        Anchor::Right => Anchor::Left,
+        Anchor::TopLeft => Anchor::TopRight,
+        Anchor::TopRight => Anchor::TopLeft,
+        Anchor::BottomLeft => Anchor::BottomRight,
+        Anchor::BottomRight => Anchor::BottomLeft,
+        other => other,
+    }
+}
+
+fn flip_anchor_y(anchor: Anchor) -> Anchor {
+    match anchor {
+        Anchor::Top => Anchor::Bottom,
+        Anchor::Bottom => Anchor::Top,
+        Anchor::TopLeft => Anchor::BottomLeft,
+        Anchor::BottomLeft => Anchor::TopLeft,
+        Anchor::TopRight => Anchor::BottomRight,
+        Anchor::BottomRight => Anchor::TopRight,
+        other => other,
+    }
+}
+
+fn flip_gravity_x(gravity: Gravity) -> Gravity {
+    match gravity {
+        Gravity::Left => Gravity::Right,
+        Gravity::Right => Gravity::Left,
+        Gravity::TopLeft => Gravity::TopRight,
+        Gravity::TopRight => Gravity::TopLeft,
+        Gravity::BottomLeft => Gravity::BottomRight,
+        Gravity::BottomRight => Gravity::BottomLeft,
+        other => other,
+    }
+}
+
+fn flip_gravity_y(gravity: Gravity) -> Gravity {
+    match gravity {
+        Gravity::Top => Gravity::Bottom,
+        Gravity::Bottom => Gravity::Top,
+        Gravity::TopLeft => Gravity::BottomLeft,
+        Gravity::BottomLeft => Gravity::TopLeft,
+        Gravity::TopRight => Gravity::BottomRight,
+        Gravity::BottomRight => Gravity::TopRight,
+        other => other,
+    }
+}
+
+fn fits_x(rect: Rectangle<i32, Logical>, work_area: Rectangle<i32, Logical>) -> bool {
+    rect.loc.x >= work_area.loc.x && rect.loc.x + rect.size.w <= work_area.loc.x + work_area.size.w
+}
+
+fn fits_y(rect: Rectangle<i32, Logical>, work_area: Rectangle<i32, Logical>) -> bool {
+    rect.loc.y >= work_area.loc.y && rect.loc.y + rect.size.h <= work_area.loc.y + work_area.size.h
+}
+
+// Slide the rect back on screen, but never past the anchor rect's opposite
+// edge -- otherwise the popup would end up no longer touching its anchor.
+fn slide_x(
+    mut rect: Rectangle<i32, Logical>,
+    anchor_rect: Rectangle<i32, Logical>,
+    work_area: Rectangle<i32, Logical>,
+) -> Rectangle<i32, Logical> {
+    let min_x = work_area.loc.x.max(anchor_rect.loc.x - rect.size.w);
+    let max_x = (work_area.loc.x + work_area.size.w - rect.size.w)
+        .min(anchor_rect.loc.x + anchor_rect.size.w);
+    if max_x >= min_x {
+        rect.loc.x = rect.loc.x.clamp(min_x, max_x);
+    }
+    rect
+}
+
+fn slide_y(
+    mut rect: Rectangle<i32, Logical>,
+    anchor_rect: Rectangle<i32, Logical>,
+    work_area: Rectangle<i32, Logical>,
+) -> Rectangle<i32, Logical> {
+    let min_y = work_area.loc.y.max(anchor_rect.loc.y - rect.size.h);
+    let max_y = (work_area.loc.y + work_area.size.h - rect.size.h)
+        .min(anchor_rect.loc.y + anchor_rect.size.h);
+    if max_y >= min_y {
+        rect.loc.y = rect.loc.y.clamp(min_y, max_y);
+    }
+    rect
+}
+
+fn resize_x(mut rect: Rectangle<i32, Logical>, work_area: Rectangle<i32, Logical>) -> Rectangle<i32, Logical> {
+    let left = rect.loc.x.max(work_area.loc.x);
+    let right = (rect.loc.x + rect.size.w).min(work_area.loc.x + work_area.size.w);
+    rect.loc.x = left;
+    rect.size.w = (right - left).max(1);
+    rect
+}
+
+fn resize_y(mut rect: Rectangle<i32, Logical>, work_area: Rectangle<i32, Logical>) -> Rectangle<i32, Logical> {
+    let top = rect.loc.y.max(work_area.loc.y);
+    let bottom = (rect.loc.y + rect.size.h).min(work_area.loc.y + work_area.size.h);
+    rect.loc.y = top;
+    rect.size.h = (bottom - top).max(1);
+    rect
+}
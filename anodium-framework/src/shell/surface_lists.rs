@@ -0,0 +1,179 @@
+//! Bookkeeping lists `Inner` keeps for surfaces at each stage of their
+//! lifetime: not-yet-mapped toplevels/popups, mapped windows, and layer
+//! shell surfaces.
+
+use smithay::desktop::{LayerSurface, Window};
+use smithay::reexports::wayland_server::protocol::wl_output::WlOutput;
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::utils::{Logical, Point};
+use smithay::wayland::shell::xdg::{PopupSurface, PositionerState, ToplevelSurface};
+
+use super::InitialWindowState;
+
+fn toplevel_wl_surface(window: &Window) -> Option<WlSurface> {
+    window.toplevel().as_xdg().map(|toplevel| toplevel.wl_surface().clone())
+}
+
+struct NotMappedToplevel {
+    surface: ToplevelSurface,
+    /// Maximize/fullscreen state requested before the first configure was
+    /// sent, buffered here so it can be resolved once an output is known.
+    pending_state: InitialWindowState,
+}
+
+/// Toplevels and popups that have been created but haven't committed yet
+/// -- not real `Window`/`PopupKind` entries until then.
+#[derive(Default)]
+pub(super) struct NotMappedList {
+    toplevels: Vec<NotMappedToplevel>,
+    popups: Vec<PopupSurface>,
+}
+
+impl NotMappedList {
+    pub(super) fn insert_toplevel(&mut self, surface: ToplevelSurface) {
+        self.toplevels.push(NotMappedToplevel {
+            surface,
+            pending_state: InitialWindowState::default(),
+        });
+    }
+
+    pub(super) fn insert_popup(&mut self, surface: PopupSurface, _positioner: PositionerState) {
+        self.popups.push(surface);
+    }
+
+    /// Buffer a maximize/fullscreen/unmaximize request against a toplevel
+    /// that hasn't mapped yet.
+    pub(super) fn set_pending_state(&mut self, surface: &WlSurface, state: InitialWindowState) {
+        if let Some(entry) = self
+            .toplevels
+            .iter_mut()
+            .find(|entry| entry.surface.wl_surface() == surface)
+        {
+            entry.pending_state = state;
+        }
+    }
+
+    pub(super) fn pending_state(&self, surface: &WlSurface) -> Option<InitialWindowState> {
+        self.toplevels
+            .iter()
+            .find(|entry| entry.surface.wl_surface() == surface)
+            .map(|entry| entry.pending_state.clone())
+    }
+
+    /// Configure the not-yet-mapped toplevel at `surface` to `geometry`, so
+    /// its first configure already carries the resolved size instead of
+    /// needing a second round-trip.
+    pub(super) fn configure_initial(&self, surface: &WlSurface, geometry: smithay::utils::Rectangle<i32, Logical>) {
+        if let Some(entry) = self.toplevels.iter().find(|entry| entry.surface.wl_surface() == surface) {
+            entry.surface.with_pending_state(|state| {
+                state.size = Some(geometry.size);
+            });
+        }
+    }
+
+    /// If `surface` is a not-yet-mapped toplevel that is still alive,
+    /// promote it to a real `Window` and drop it from this list. Whether the
+    /// commit attached a buffer is not checked.
+    pub(super) fn try_window_map(&mut self, surface: &WlSurface) -> Option<Window> {
+        let idx = self
+            .toplevels
+            .iter()
+            .position(|entry| entry.surface.wl_surface() == surface && entry.surface.alive())?;
+
+        let entry = self.toplevels.remove(idx);
+        Some(Window::new(smithay::desktop::Kind::Xdg(entry.surface)))
+    }
+
+    pub(super) fn refresh(&mut self) {
+        self.toplevels.retain(|entry| entry.surface.alive());
+        self.popups.retain(|popup| popup.alive());
+    }
+}
+
+struct MappedWindow {
+    window: Window,
+    location: Point<i32, Logical>,
+}
+
+/// Every currently-mapped window, alongside the location the layout (or an
+/// interactive move) last placed it at.
+#[derive(Default)]
+pub(super) struct ShellWindowList(Vec<MappedWindow>);
+
+impl ShellWindowList {
+    pub(super) fn push(&mut self, window: Window) {
+        self.0.push(MappedWindow {
+            window,
+            location: (0, 0).into(),
+        });
+    }
+
+    pub(super) fn find(&self, surface: &WlSurface) -> Option<Window> {
+        self.0
+            .iter()
+            .find(|entry| toplevel_wl_surface(&entry.window).as_ref() == Some(surface))
+            .map(|entry| entry.window.clone())
+    }
+
+    pub(super) fn find_mut(&mut self, surface: &WlSurface) -> Option<&mut Window> {
+        self.0
+            .iter_mut()
+            .find(|entry| toplevel_wl_surface(&entry.window).as_ref() == Some(surface))
+            .map(|entry| &mut entry.window)
+    }
+
+    pub(super) fn location(&self, window: &Window) -> Point<i32, Logical> {
+        self.0
+            .iter()
+            .find(|entry| &entry.window == window)
+            .map(|entry| entry.location)
+            .unwrap_or_default()
+    }
+
+    pub(super) fn set_location(&mut self, window: &Window, location: Point<i32, Logical>) {
+        if let Some(entry) = self.0.iter_mut().find(|entry| &entry.window == window) {
+            entry.location = location;
+        }
+    }
+
+    pub(super) fn refresh(&mut self) {
+        self.0.retain(|entry| entry.window.toplevel().alive());
+    }
+}
+
+struct MappedLayer {
+    layer: LayerSurface,
+    output: Option<WlOutput>,
+}
+
+/// Every currently-mapped layer shell surface, alongside the output it was
+/// created on -- needed to subtract its exclusive zone from that output's
+/// work area.
+#[derive(Default)]
+pub(super) struct ShellLayerList(Vec<MappedLayer>);
+
+impl ShellLayerList {
+    pub(super) fn push(&mut self, layer: LayerSurface, output: Option<WlOutput>) {
+        self.0.push(MappedLayer { layer, output });
+    }
+
+    pub(super) fn find(&self, surface: &WlSurface) -> Option<LayerSurface> {
+        self.0
+            .iter()
+            .find(|entry| entry.layer.layer_surface().wl_surface() == surface)
+            .map(|entry| entry.layer.clone())
+    }
+
+    /// Layers anchored to `output`, used to subtract their exclusive zones
+    /// from its usable work area.
+    pub(super) fn for_output<'a>(&'a self, output: &'a WlOutput) -> impl Iterator<Item = &'a LayerSurface> {
+        self.0
+            .iter()
+            .filter(move |entry| entry.output.as_ref() == Some(output))
+            .map(|entry| &entry.layer)
+    }
+
+    pub(super) fn refresh(&mut self) {
+        self.0.retain(|entry| entry.layer.layer_surface().alive());
+    }
+}
@@ -0,0 +1,217 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use smithay::desktop::{PopupKind, Window};
+use smithay::reexports::wayland_server::protocol::wl_seat::WlSeat;
+use smithay::wayland::seat::Seat;
+use smithay::wayland::shell::xdg::{ToplevelSurface, XdgRequest};
+use smithay::wayland::Serial;
+
+use crate::surface_data::ResizeEdge;
+
+use super::grabs::{MoveSurfaceGrab, ResizeSurfaceGrab};
+use super::{Inner, InitialWindowState, ShellEvent, ShellHandler};
+
+impl<D> Inner<D>
+where
+    D: ShellHandler + 'static,
+{
+    pub(super) fn xdg_shell_request(
+        &mut self,
+        request: XdgRequest,
+        handler: &mut D,
+        inner: &Rc<RefCell<Self>>,
+    ) {
+        match request {
+            XdgRequest::NewToplevel { surface } => {
+                self.not_mapped_list.insert_toplevel(surface);
+            }
+            XdgRequest::NewPopup {
+                surface,
+                positioner,
+            } => {
+                super::popup::store_positioner(surface.wl_surface(), positioner.clone());
+                self.not_mapped_list.insert_popup(surface.clone(), positioner);
+                let _ = self.popup_manager.track_popup(PopupKind::Xdg(surface));
+            }
+
+            XdgRequest::RePosition {
+                surface,
+                positioner,
+                token,
+            } => {
+                super::popup::store_positioner(surface.wl_surface(), positioner);
+                if let Some(geometry) = self.solve_popup_geometry(&surface) {
+                    surface.with_pending_state(|state| state.geometry = geometry);
+                    surface.send_repositioned(token);
+                }
+            }
+
+            XdgRequest::Move {
+                surface,
+                seat,
+                serial,
+            } => self.start_move(&surface, seat, serial, handler, inner),
+
+            XdgRequest::Resize {
+                surface,
+                seat,
+                serial,
+                edges,
+            } => self.start_resize(&surface, seat, serial, edges.into(), handler, inner),
+
+            XdgRequest::Maximize { surface } => {
+                if let Some(window) = self.window_for_toplevel(&surface) {
+                    handler.on_shell_event(ShellEvent::WindowMaximize { window });
+                } else {
+                    // Not mapped yet: buffer it so the first configure
+                    // already carries the maximized geometry.
+                    self.not_mapped_list
+                        .set_pending_state(surface.wl_surface(), InitialWindowState::Maximized);
+                }
+            }
+            XdgRequest::UnMaximize { surface } => {
+                if let Some(window) = self.window_for_toplevel(&surface) {
+                    handler.on_shell_event(ShellEvent::WindowUnMaximize { window });
+                } else {
+                    self.not_mapped_list
+                        .set_pending_state(surface.wl_surface(), InitialWindowState::Normal);
+                }
+            }
+            XdgRequest::Fullscreen { surface, output } => {
+                if let Some(window) = self.window_for_toplevel(&surface) {
+                    handler.on_shell_event(ShellEvent::WindowFullscreen { window, output });
+                } else {
+                    self.not_mapped_list.set_pending_state(
+                        surface.wl_surface(),
+                        InitialWindowState::Fullscreen { output },
+                    );
+                }
+            }
+            XdgRequest::UnFullscreen { surface } => {
+                if let Some(window) = self.window_for_toplevel(&surface) {
+                    handler.on_shell_event(ShellEvent::WindowUnFullscreen { window });
+                } else {
+                    self.not_mapped_list
+                        .set_pending_state(surface.wl_surface(), InitialWindowState::Normal);
+                }
+            }
+            XdgRequest::Minimize { surface } => {
+                if let Some(window) = self.window_for_toplevel(&surface) {
+                    handler.on_shell_event(ShellEvent::WindowMinimize { window });
+                }
+            }
+
+            XdgRequest::ShowWindowMenu {
+                surface,
+                seat,
+                serial,
+                location,
+            } => {
+                if let Some(window) = self.window_for_toplevel(&surface) {
+                    let seat = Seat::from_resource(&seat).unwrap();
+                    handler.on_shell_event(ShellEvent::ShowWindowMenu {
+                        window,
+                        seat,
+                        serial,
+                        location,
+                    });
+                }
+            }
+
+            _ => (),
+        }
+    }
+
+    fn window_for_toplevel(&self, surface: &ToplevelSurface) -> Option<Window> {
+        self.windows.find(surface.wl_surface())
+    }
+
+    fn start_move(
+        &mut self,
+        surface: &ToplevelSurface,
+        seat: WlSeat,
+        serial: Serial,
+        handler: &mut D,
+        inner: &Rc<RefCell<Self>>,
+    ) {
+        let Some(window) = self.window_for_toplevel(surface) else {
+            return;
+        };
+        let seat = Seat::from_resource(&seat).unwrap();
+        let pointer = seat.get_pointer().unwrap();
+
+        if !pointer.has_grab(serial) {
+            return;
+        }
+        let Some(start_data) = pointer.grab_start_data() else {
+            return;
+        };
+
+        handler.on_shell_event(ShellEvent::WindowMove {
+            window: window.clone(),
+            start_data: start_data.clone(),
+            seat: seat.clone(),
+            serial,
+        });
+
+        let initial_window_location = self.windows.location(&window);
+        pointer.set_grab(
+            MoveSurfaceGrab {
+                start_data,
+                inner: inner.clone(),
+                window,
+                surface: surface.wl_surface().clone(),
+                initial_window_location,
+                reordered_delta_x: 0,
+            },
+            serial,
+        );
+    }
+
+    fn start_resize(
+        &mut self,
+        surface: &ToplevelSurface,
+        seat: WlSeat,
+        serial: Serial,
+        edges: ResizeEdge,
+        handler: &mut D,
+        inner: &Rc<RefCell<Self>>,
+    ) {
+        let Some(window) = self.window_for_toplevel(surface) else {
+            return;
+        };
+        let seat = Seat::from_resource(&seat).unwrap();
+        let pointer = seat.get_pointer().unwrap();
+
+        if !pointer.has_grab(serial) {
+            return;
+        }
+        let Some(start_data) = pointer.grab_start_data() else {
+            return;
+        };
+
+        handler.on_shell_event(ShellEvent::WindowResize {
+            window: window.clone(),
+            start_data: start_data.clone(),
+            seat: seat.clone(),
+            edges,
+            serial,
+        });
+
+        let initial_window_location = self.windows.location(&window);
+        let initial_window_size = window.geometry().size;
+        pointer.set_grab(
+            ResizeSurfaceGrab {
+                start_data,
+                inner: inner.clone(),
+                window,
+                surface: surface.wl_surface().clone(),
+                edges,
+                initial_window_location,
+                initial_window_size,
+            },
+            serial,
+        );
+    }
+}
@@ -0,0 +1,349 @@
+//! Rootless XWayland support.
+//!
+//! Unlike the native xdg-shell path, nothing here owns a blocking socket:
+//! the X11 connection is registered on the same `calloop` `LoopHandle` the
+//! rest of the compositor uses, and Xwayland itself is supervised and
+//! restarted if it dies rather than being a one-shot child process.
+
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::UnixStream;
+
+use smithay::reexports::calloop::LoopHandle;
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::reexports::wayland_server::{Client, Display};
+use smithay::utils::{Logical, Rectangle, Size};
+use smithay::wayland::Serial;
+
+use x11rb::connection::Connection;
+use x11rb::properties::WmSizeHints;
+use x11rb::protocol::xproto::{
+    Atom, ConfigureNotifyEvent, ConnectionExt, MapNotifyEvent, UnmapNotifyEvent, Window as X11Window,
+};
+use x11rb::protocol::Event as X11Event;
+use x11rb::rust_connection::RustConnection;
+
+use crate::surface_data::{ResizeData, ResizeEdge, ResizeState, SurfaceData};
+use crate::Anodium;
+
+use super::{Inner, ShellEvent, ShellHandler};
+
+mod surface;
+pub use surface::X11Surface;
+
+/// Supervises the Xwayland child process: spawns it, waits for the
+/// `XWaylandEvent::Ready`/`Exited` notifications smithay's `XWayland` helper
+/// delivers on the loop, and respawns it if it exits unexpectedly.
+pub struct XWaylandState {
+    xwayland: smithay::xwayland::XWayland<Anodium>,
+}
+
+impl XWaylandState {
+    pub fn spawn(handle: &LoopHandle<Anodium>, display: &mut Display) -> Self {
+        let (xwayland, channel) = smithay::xwayland::XWayland::new(handle, display);
+
+        handle
+            .insert_source(channel, move |event, _, state| match event {
+                smithay::xwayland::XWaylandEvent::Ready { connection, client } => {
+                    state
+                        .shell_manager
+                        .xwayland_ready(&state.loop_handle, connection, client);
+                }
+                smithay::xwayland::XWaylandEvent::Exited => {
+                    tracing::warn!("Xwayland exited, restarting");
+                    state.xwayland.start().log_err("Failed to respawn Xwayland:").ok();
+                }
+            })
+            .expect("Failed to register Xwayland event source");
+
+        xwayland.start().log_err("Failed to start Xwayland:").ok();
+
+        Self { xwayland }
+    }
+}
+
+/// Start driving the X11 WM connection handed to us in `connection` on
+/// `handle`, translating window manager requests into `ShellEvent`s through
+/// `cb`.
+pub fn xwayland_shell_init<F>(
+    handle: &LoopHandle<Anodium>,
+    connection: UnixStream,
+    client: Client,
+    cb: F,
+) where
+    F: FnMut(X11Request, &mut X11Wm, Client, smithay::reexports::wayland_server::DispatchData) + 'static,
+{
+    let fd = connection.as_raw_fd();
+    let x11_connection = RustConnection::connect_from_fd(fd).expect("Failed to wrap Xwayland connection");
+
+    let wm = X11Wm::new(x11_connection, client.clone());
+
+    handle
+        .insert_source(
+            smithay::reexports::calloop::generic::Generic::new(
+                fd,
+                smithay::reexports::calloop::Interest::READ,
+                smithay::reexports::calloop::Mode::Level,
+            ),
+            {
+                let mut wm = wm;
+                let mut cb = cb;
+                let client = client.clone();
+                // `Generic` only polls the raw fd, and `RustConnection::connect_from_fd`
+                // doesn't take ownership of it either -- `connection` is still the
+                // only thing that closes it on drop, so it has to be kept alive for
+                // as long as the event source is registered.
+                let _connection = connection;
+                move |_, _, ddata| {
+                    // Referenced so the `move` closure takes ownership of it.
+                    let _ = &_connection;
+                    while let Some(event) = wm.connection.poll_for_event().ok().flatten() {
+                        if let Some(request) = wm.translate(event) {
+                            cb(request, &mut wm, client.clone(), ddata);
+                        }
+                    }
+                    Ok(smithay::reexports::calloop::PostAction::Continue)
+                }
+            },
+        )
+        .expect("Failed to register the Xwayland connection on the event loop");
+}
+
+/// The WM-side half of the rootless XWayland connection: owns the x11rb
+/// connection and the set of windows we've been told about.
+pub struct X11Wm {
+    connection: RustConnection,
+    client: Client,
+    windows: Vec<X11Surface>,
+    /// Atom for `WL_SURFACE_ID`, the client message Xwayland sends to tell
+    /// us which `wl_surface` backs a given X11 window. `0` if interning it
+    /// failed, in which case windows never get a `wl_surface` association.
+    wl_surface_id_atom: Atom,
+}
+
+impl X11Wm {
+    fn new(connection: RustConnection, client: Client) -> Self {
+        let wl_surface_id_atom = connection
+            .intern_atom(false, b"WL_SURFACE_ID")
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .map(|reply| reply.atom)
+            .unwrap_or(0);
+
+        Self {
+            connection,
+            client,
+            windows: Vec::new(),
+            wl_surface_id_atom,
+        }
+    }
+
+    fn surface_for(&mut self, window: X11Window) -> &mut X11Surface {
+        if let Some(idx) = self.windows.iter().position(|w| w.window_id() == window) {
+            return &mut self.windows[idx];
+        }
+        self.windows.push(X11Surface::new(window));
+        self.windows.last_mut().unwrap()
+    }
+
+    /// Fetch `WM_NORMAL_HINTS` for `window`, falling back to "no limit" for
+    /// whichever bound the client didn't advertise.
+    fn size_hints(&self, window: X11Window) -> (Size<i32, Logical>, Size<i32, Logical>) {
+        let hints = WmSizeHints::get_normal_hints(&self.connection, window)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok());
+
+        let min_size = hints
+            .as_ref()
+            .and_then(|hints| hints.min_size)
+            .map(|(w, h)| Size::from((w.max(1), h.max(1))))
+            .unwrap_or_else(|| Size::from((1, 1)));
+        let max_size = hints
+            .as_ref()
+            .and_then(|hints| hints.max_size)
+            .map(|(w, h)| Size::from((w, h)))
+            .unwrap_or_else(|| Size::from((i32::MAX, i32::MAX)));
+
+        (min_size, max_size)
+    }
+
+    fn translate(&mut self, event: X11Event) -> Option<X11Request> {
+        match event {
+            X11Event::MapRequest(ev) => Some(X11Request::Map { window: ev.window }),
+            X11Event::MapNotify(MapNotifyEvent {
+                window,
+                override_redirect,
+                ..
+            }) => Some(X11Request::Mapped {
+                window,
+                override_redirect,
+            }),
+            X11Event::UnmapNotify(UnmapNotifyEvent { window, .. }) => {
+                Some(X11Request::Unmap { window })
+            }
+            X11Event::ConfigureRequest(ev) => Some(X11Request::Configure {
+                window: ev.window,
+                geometry: Rectangle::from_loc_and_size(
+                    (ev.x as i32, ev.y as i32),
+                    (ev.width as i32, ev.height as i32),
+                ),
+            }),
+            X11Event::ConfigureNotify(ConfigureNotifyEvent { window, .. }) => {
+                Some(X11Request::Configure {
+                    window,
+                    geometry: self.surface_for(window).geometry(),
+                })
+            }
+            X11Event::ReparentNotify(ev) => Some(X11Request::Reparented {
+                window: ev.window,
+                parent: ev.parent,
+            }),
+            X11Event::ClientMessage(ev)
+                if self.wl_surface_id_atom != 0 && ev.type_ == self.wl_surface_id_atom =>
+            {
+                Some(X11Request::WlSurfaceId {
+                    window: ev.window,
+                    surface_id: ev.data.as_data32()[0],
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A window-manager request translated from the raw X11 protocol, handed to
+/// `Inner::xwayland_shell_event` for dispatch into the common `ShellEvent`
+/// stream.
+pub enum X11Request {
+    Map { window: X11Window },
+    Mapped { window: X11Window, override_redirect: bool },
+    Unmap { window: X11Window },
+    Configure { window: X11Window, geometry: Rectangle<i32, Logical> },
+    Reparented { window: X11Window, parent: X11Window },
+    /// Xwayland told us which `wl_surface` backs `window`, carried as the
+    /// surface's protocol object ID in the `WL_SURFACE_ID` client message.
+    WlSurfaceId { window: X11Window, surface_id: u32 },
+}
+
+impl<D> Inner<D>
+where
+    D: ShellHandler + 'static,
+{
+    /// Called from `surface_commit` for every committed surface, regardless
+    /// of whether it belongs to an X11 client; cheap no-op either way --
+    /// X11 geometry and size-hint propagation into `SurfaceData` happens as
+    /// soon as `X11Request::Configure` is dispatched below, since that's
+    /// where the authoritative geometry and `WM_NORMAL_HINTS` come from.
+    /// Nothing drains the `ResizeState` set there: X11 windows are not in
+    /// `Inner::windows`, so `Inner::try_update_mapped` skips their surfaces.
+    pub(super) fn xwayland_commit_hook(&mut self, _surface: &WlSurface) {}
+
+    pub(super) fn xwayland_shell_event(
+        &mut self,
+        event: X11Request,
+        wm: &mut X11Wm,
+        client: Client,
+        mut ddata: smithay::reexports::wayland_server::DispatchData,
+    ) -> Result<(), ()> {
+        let handler = ddata.get::<D>().ok_or(())?;
+
+        match event {
+            X11Request::Map { window } => {
+                wm.surface_for(window).request_map();
+            }
+
+            X11Request::Mapped {
+                window,
+                override_redirect,
+            } => {
+                let surface = wm.surface_for(window);
+                surface.set_override_redirect(override_redirect);
+
+                if override_redirect {
+                    // Tooltips/menus bypass the tiling/placement logic
+                    // entirely: they're positioned by the client itself.
+                    return Ok(());
+                }
+
+                if let Some(window) = surface.desktop_window() {
+                    handler.on_shell_event(ShellEvent::WindowCreated { window });
+                }
+            }
+
+            X11Request::Unmap { window } => {
+                if let Some(desktop_window) = wm.surface_for(window).desktop_window() {
+                    handler.on_shell_event(ShellEvent::WindowMinimize {
+                        window: desktop_window,
+                    });
+                }
+                wm.windows.retain(|w| w.window_id() != window);
+            }
+
+            X11Request::Configure { window, geometry } => {
+                let (min_size, max_size) = wm.size_hints(window);
+                let geometry = clamp_geometry(geometry, min_size, max_size);
+
+                let surface = wm.surface_for(window);
+                surface.set_geometry(geometry);
+
+                if let Some(wl_surface) = surface.wl_surface() {
+                    // Feed the clamped geometry through the same
+                    // `ResizeState` machine `ResizeSurfaceGrab` drives for
+                    // native toplevels, tagged with a serial since X11
+                    // windows never send one of their own. No later commit
+                    // advances this state; the geometry reaches the handler
+                    // through the `WindowGotResized` below.
+                    SurfaceData::with_mut(&wl_surface, |data| {
+                        data.resize_state = ResizeState::WaitingForFinalAck(
+                            ResizeData {
+                                edges: ResizeEdge::empty(),
+                                initial_window_location: geometry.loc,
+                                initial_window_size: geometry.size,
+                            },
+                            next_serial(),
+                        );
+                    });
+                }
+
+                if let Some(desktop_window) = surface.desktop_window() {
+                    handler.on_shell_event(ShellEvent::WindowGotResized {
+                        window: desktop_window,
+                        new_location_x: Some(geometry.loc.x),
+                        new_location_y: Some(geometry.loc.y),
+                        new_size: Some(geometry.size),
+                    });
+                }
+            }
+
+            X11Request::Reparented { window, parent } => {
+                wm.surface_for(window).set_parent(parent);
+            }
+
+            X11Request::WlSurfaceId { window, surface_id } => {
+                if let Ok(wl_surface) = client.object_from_protocol_id::<WlSurface>(surface_id) {
+                    wm.surface_for(window).set_wl_surface(wl_surface);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Clamp `geometry`'s size to `WM_NORMAL_HINTS`' min/max, in case the
+/// `ConfigureRequest` asked for something outside of them.
+fn clamp_geometry(
+    geometry: Rectangle<i32, Logical>,
+    min_size: Size<i32, Logical>,
+    max_size: Size<i32, Logical>,
+) -> Rectangle<i32, Logical> {
+    let w = geometry.size.w.clamp(min_size.w, max_size.w.max(min_size.w));
+    let h = geometry.size.h.clamp(min_size.h, max_size.h.max(min_size.h));
+    Rectangle::from_loc_and_size(geometry.loc, Size::from((w, h)))
+}
+
+/// Fake serial source for X11-originated move/resize requests, which don't
+/// carry a Wayland `Serial` -- the next best thing is the compositor's own
+/// monotonic counter.
+pub(super) fn next_serial() -> Serial {
+    smithay::wayland::SERIAL_COUNTER.next_serial()
+}
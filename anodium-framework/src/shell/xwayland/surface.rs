@@ -0,0 +1,108 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use smithay::desktop::{Kind, Window};
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::utils::{Logical, Rectangle};
+
+use x11rb::protocol::xproto::Window as X11Window;
+
+struct Inner {
+    window: X11Window,
+    parent: Option<X11Window>,
+    override_redirect: bool,
+    mapped: bool,
+    geometry: Rectangle<i32, Logical>,
+    wl_surface: Option<WlSurface>,
+    /// The `desktop::Window` this surface is exposed as, created once and
+    /// cached -- `Window` identity is by handle, so handing out a fresh one
+    /// on every call would break every later lookup keyed by equality.
+    desktop_window: Option<Window>,
+}
+
+/// Bookkeeping for a single X11 window, rootless-managed through Xwayland.
+///
+/// Override-redirect windows (tooltips, menus, ...) are tracked here too,
+/// but never get a `desktop::Window` of their own: they position
+/// themselves and must bypass the tiling/placement path entirely.
+///
+/// Shares its state through an `Rc<RefCell<_>>` (same pattern `ShellManager`
+/// uses for `Inner`) so the clone embedded in a cached `Window`'s
+/// `Kind::Xwayland` stays in sync with `X11Wm`'s copy instead of drifting
+/// into a stale snapshot.
+#[derive(Clone)]
+pub struct X11Surface {
+    inner: Rc<RefCell<Inner>>,
+}
+
+impl X11Surface {
+    pub(super) fn new(window: X11Window) -> Self {
+        Self {
+            inner: Rc::new(RefCell::new(Inner {
+                window,
+                parent: None,
+                override_redirect: false,
+                mapped: false,
+                geometry: Rectangle::from_loc_and_size((0, 0), (0, 0)),
+                wl_surface: None,
+                desktop_window: None,
+            })),
+        }
+    }
+
+    pub fn window_id(&self) -> X11Window {
+        self.inner.borrow().window
+    }
+
+    pub fn is_override_redirect(&self) -> bool {
+        self.inner.borrow().override_redirect
+    }
+
+    pub fn geometry(&self) -> Rectangle<i32, Logical> {
+        self.inner.borrow().geometry
+    }
+
+    /// The Wayland surface Xwayland associated with this window via a
+    /// `WL_SURFACE_ID` client message, once it has arrived.
+    pub fn wl_surface(&self) -> Option<WlSurface> {
+        self.inner.borrow().wl_surface.clone()
+    }
+
+    pub(super) fn set_override_redirect(&mut self, override_redirect: bool) {
+        let mut inner = self.inner.borrow_mut();
+        inner.override_redirect = override_redirect;
+        inner.mapped = true;
+    }
+
+    pub(super) fn set_geometry(&mut self, geometry: Rectangle<i32, Logical>) {
+        self.inner.borrow_mut().geometry = geometry;
+    }
+
+    pub(super) fn set_parent(&mut self, parent: X11Window) {
+        self.inner.borrow_mut().parent = Some(parent);
+    }
+
+    pub(super) fn set_wl_surface(&mut self, surface: WlSurface) {
+        self.inner.borrow_mut().wl_surface = Some(surface);
+    }
+
+    pub(super) fn request_map(&mut self) {
+        self.inner.borrow_mut().mapped = true;
+    }
+
+    /// The `desktop::Window` this surface flows through the compositor as,
+    /// so that X11 clients share the exact same placement/configure path as
+    /// native Wayland toplevels. `None` for override-redirect windows and
+    /// for windows that have seen neither a map request nor a map notify.
+    pub fn desktop_window(&self) -> Option<Window> {
+        let mut inner = self.inner.borrow_mut();
+        if inner.override_redirect || !inner.mapped {
+            return None;
+        }
+
+        if inner.desktop_window.is_none() {
+            inner.desktop_window = Some(Window::new(Kind::Xwayland(self.clone())));
+        }
+        inner.desktop_window.clone()
+    }
+}
@@ -0,0 +1,16 @@
+/// High-level compositor actions a keybinding can trigger, dispatched
+/// alongside `ShellEvent`s once a chord matches in [`crate::data::seat::SeatState`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompositorAction {
+    CloseActiveWindow,
+    FocusColumn(Direction),
+    ToggleMaximize,
+    ToggleFullscreen,
+    MoveActiveWindowToOutput(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+}
@@ -5,16 +5,28 @@ use std::{
 
 use smithay::{
     backend::input::KeyState,
-    input::Seat,
+    input::{keyboard::ModifiersState, Seat},
     utils::{Logical, Point},
 };
 
+use crate::action::CompositorAction;
 use crate::State;
 
+/// A registered chord: the modifier mask plus keysym that must be pressed
+/// together to trigger `action`.
+#[derive(Debug, Clone)]
+struct KeyBinding {
+    modifiers: ModifiersState,
+    keysym: u32,
+    suppress: bool,
+    action: CompositorAction,
+}
+
 #[derive(Debug, Default)]
 pub struct SeatState {
     pointer_pos: Cell<Point<f64, Logical>>,
     pressed_keys: RefCell<HashSet<u32>>,
+    bindings: RefCell<Vec<KeyBinding>>,
 }
 
 impl SeatState {
@@ -38,4 +50,42 @@ impl SeatState {
             self.pressed_keys.borrow_mut().remove(&keysym);
         }
     }
+
+    /// Install a keybinding: `modifiers` + `keysym` triggers `action`. When
+    /// `suppress` is set the matching keypress is not forwarded to the
+    /// focused client.
+    pub fn register_binding(
+        &self,
+        modifiers: ModifiersState,
+        keysym: u32,
+        suppress: bool,
+        action: CompositorAction,
+    ) {
+        self.bindings.borrow_mut().push(KeyBinding {
+            modifiers,
+            keysym,
+            suppress,
+            action,
+        });
+    }
+
+    /// Match the current modifier mask and the keysym that was just pressed
+    /// against the registered bindings. Returns the action and whether the
+    /// keypress should be suppressed from reaching the focused surface.
+    pub fn match_binding(&self, modifiers: ModifiersState, keysym: u32) -> Option<(CompositorAction, bool)> {
+        self.bindings
+            .borrow()
+            .iter()
+            .find(|binding| {
+                relevant_modifiers(binding.modifiers) == relevant_modifiers(modifiers) && binding.keysym == keysym
+            })
+            .map(|binding| (binding.action, binding.suppress))
+    }
+}
+
+/// The modifier bits that make up a chord, ignoring lock modifiers (caps
+/// lock, num lock) -- those toggle independently of what's actually held
+/// down and shouldn't affect binding matching.
+fn relevant_modifiers(modifiers: ModifiersState) -> (bool, bool, bool, bool) {
+    (modifiers.ctrl, modifiers.alt, modifiers.shift, modifiers.logo)
 }
@@ -0,0 +1,87 @@
+use smithay::backend::input::{InputBackend, KeyState, KeyboardKeyEvent};
+use smithay::input::keyboard::FilterResult;
+use smithay::input::Seat;
+use smithay::utils::SERIAL_COUNTER;
+
+use crate::action::{CompositorAction, Direction};
+use crate::data::seat::SeatState;
+use crate::shell::ShellEvent;
+use crate::State;
+
+impl State {
+    /// Forward a keyboard key event to the focused client, unless it
+    /// completes a registered chord -- in which case the action is
+    /// dispatched and, if the binding asks for it, the press is swallowed
+    /// instead of being forwarded.
+    pub fn handle_keyboard_key<B: InputBackend>(&mut self, seat: &Seat<Self>, event: impl KeyboardKeyEvent<B>) {
+        let keycode = event.key_code();
+        let key_state = event.state();
+        let time = event.time_msec();
+
+        let Some(keyboard) = seat.get_keyboard() else {
+            return;
+        };
+        let seat_state = SeatState::for_seat(seat);
+        let mut matched = None;
+
+        keyboard.input::<(), _>(
+            self,
+            keycode,
+            key_state,
+            SERIAL_COUNTER.next_serial(),
+            time,
+            |_, modifiers, keysym_handle| {
+                // The raw, unmodified keysym -- bindings are registered
+                // against e.g. `q`, and matching `modified_sym()` instead
+                // would require a binding for `Q` whenever shift is part of
+                // the chord (like the `super+shift+q` example this
+                // subsystem was built for).
+                let keysym = keysym_handle
+                    .raw_syms()
+                    .first()
+                    .copied()
+                    .unwrap_or_else(|| keysym_handle.modified_sym());
+                seat_state.update_pressed_keys(keysym, key_state);
+
+                if let KeyState::Released = key_state {
+                    return FilterResult::Forward;
+                }
+
+                match seat_state.match_binding(*modifiers, keysym) {
+                    Some((action, suppress)) => {
+                        matched = Some(action);
+                        if suppress {
+                            FilterResult::Intercept(())
+                        } else {
+                            FilterResult::Forward
+                        }
+                    }
+                    None => FilterResult::Forward,
+                }
+            },
+        );
+
+        if let Some(action) = matched {
+            self.handle_action(action);
+        }
+    }
+
+    /// Translate a matched keybinding into its `ShellEvent` and dispatch it
+    /// through the same channel shell protocol requests go through, rather
+    /// than poking window state directly from the input handler.
+    fn handle_action(&mut self, action: CompositorAction) {
+        let event = match action {
+            CompositorAction::CloseActiveWindow => ShellEvent::CloseActiveWindow,
+            CompositorAction::FocusColumn(direction) => ShellEvent::FocusColumn {
+                towards_right: direction == Direction::Right,
+            },
+            CompositorAction::ToggleMaximize => ShellEvent::ToggleMaximizeActiveWindow,
+            CompositorAction::ToggleFullscreen => ShellEvent::ToggleFullscreenActiveWindow,
+            CompositorAction::MoveActiveWindowToOutput(index) => {
+                ShellEvent::MoveActiveWindowToOutput { index }
+            }
+        };
+
+        self.on_shell_event(event);
+    }
+}